@@ -0,0 +1,59 @@
+// Probes which optional WASI syscall surfaces the active target links (and,
+// when a WASI runtime is on PATH, runs without trapping), and gates the corresponding subcommands in wasi.rs via
+// `cargo:rustc-cfg` so this fixture still builds on stripped-down WASI
+// runtimes while exposing richer commands where supported.
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let target = env::var("TARGET").unwrap();
+
+    println!("cargo::rustc-check-cfg=cfg(has_readlink)");
+    println!("cargo::rustc-check-cfg=cfg(has_symlink_metadata)");
+
+    if probe(&out_dir, &target, "has_readlink", "fs::read_link(\".\").ok();") {
+        println!("cargo:rustc-cfg=has_readlink");
+    }
+    if probe(
+        &out_dir,
+        &target,
+        "has_symlink_metadata",
+        "fs::symlink_metadata(\".\").ok();",
+    ) {
+        println!("cargo:rustc-cfg=has_symlink_metadata");
+    }
+}
+
+// Compiles a tiny program exercising `body` against `target`, then, if a
+// WASI runtime (wasmtime) is on PATH, runs the compiled module as an extra
+// smoke check. `body` swallows its result with `.ok()`, so a clean exit only
+// proves the module instantiates and runs without trapping — it does not
+// prove the underlying syscall succeeded (ENOSYS and friends still exit 0).
+// Compiling against `target` remains the actual feature signal; the try-run
+// step just catches a std API that links but faults at startup. Falls back
+// to the compile result when no runtime is available to run against.
+fn probe(out_dir: &str, target: &str, name: &str, body: &str) -> bool {
+    let src_path = Path::new(out_dir).join(format!("probe_{}.rs", name));
+    fs::write(&src_path, format!("use std::fs; fn main() {{ {} }}", body)).unwrap();
+
+    let out_path = Path::new(out_dir).join(format!("probe_{}.wasm", name));
+    let compiled = Command::new(env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string()))
+        .args(&["--target", target, "--crate-type", "bin", "-o"])
+        .arg(&out_path)
+        .arg(&src_path)
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false);
+
+    if !compiled {
+        return false;
+    }
+
+    match Command::new("wasmtime").arg(&out_path).status() {
+        Ok(status) => status.success(),
+        Err(_) => compiled,
+    }
+}
@@ -2,6 +2,9 @@ use std::env;
 use std::fs;
 use std::io;
 use std::io::Write;
+#[cfg(has_symlink_metadata)]
+use std::os::wasi::fs::FileTypeExt;
+use std::path::Path;
 use std::process::exit;
 
 // Until NotADirectory is implemented, read the underlying error raised by
@@ -11,51 +14,255 @@ use libc::ENOTDIR;
 fn main() {
     let args: Vec<String> = env::args().collect();
 
-    match args[1].as_str() {
+    let code = match args[1].as_str() {
         "ls" => {
-            main_ls(&args[2]);
+            let mut code = main_ls(&args[2]);
             if args.len() == 4 && args[3].as_str() == "repeat" {
-                main_ls(&args[2]);
+                code = main_ls(&args[2]);
             }
+            code
         }
-        "stat" => {
-            main_stat();
+        "stat" => main_stat(),
+        "walk" => {
+            let mut max_depth: Option<usize> = None;
+            if args.len() == 5 && args[3].as_str() == "--max-depth" {
+                max_depth = Some(args[4].parse().expect("--max-depth wants an integer"));
+            }
+            main_walk(&args[2], max_depth)
+        }
+        #[cfg(has_symlink_metadata)]
+        "filetype" => {
+            let follow = match args.get(3).map(|s| s.as_str()) {
+                Some("--follow") | None => true,
+                Some("--no-follow") => false,
+                Some(mode) => {
+                    writeln!(io::stderr(), "unknown filetype mode: {}", mode).unwrap();
+                    exit(1);
+                }
+            };
+            main_filetype(&args[2], follow)
         }
+        #[cfg(has_symlink_metadata)]
+        "exists" => main_exists(&args[2]),
+        #[cfg(has_readlink)]
+        "readlink" => main_readlink(&args[2]),
+        "caps" => main_caps(),
         _ => {
             writeln!(io::stderr(), "unknown command: {}", args[1]).unwrap();
             exit(1);
         }
+    };
+    exit(code);
+}
+
+// Maps a raw errno to its symbolic name, so test output stays readable and
+// comparable across WASI runtimes that renumber errnos.
+fn errno_name(error_code: i32) -> Option<&'static str> {
+    match error_code {
+        _ if error_code == libc::ENOENT => Some("ENOENT"),
+        _ if error_code == libc::EACCES => Some("EACCES"),
+        _ if error_code == libc::EISDIR => Some("EISDIR"),
+        _ if error_code == ENOTDIR => Some("ENOTDIR"),
+        _ if error_code == libc::EBADF => Some("EBADF"),
+        _ if error_code == libc::ELOOP => Some("ELOOP"),
+        _ => None,
     }
 }
 
-fn main_ls(dir_name: &String) {
+// Exit codes, documented so k6's WASI host tests can assert on the child's
+// exit status instead of string-scraping stdout:
+//   0 success
+//   1 generic error
+//   2 not found
+//   3 not a directory
+//   4 permission denied
+//   5 bad file descriptor
+fn exit_code(error_code: i32) -> i32 {
+    if error_code == libc::ENOENT {
+        2
+    } else if error_code == ENOTDIR {
+        3
+    } else if error_code == libc::EACCES {
+        4
+    } else if error_code == libc::EBADF {
+        5
+    } else {
+        1
+    }
+}
+
+fn print_io_error(e: &io::Error) -> i32 {
+    match e.raw_os_error() {
+        Some(error_code) => {
+            match errno_name(error_code) {
+                Some(name) => println!("{}", name),
+                None => println!("errno=={}", error_code),
+            }
+            exit_code(error_code)
+        }
+        None => {
+            println!("unknown error");
+            1
+        }
+    }
+}
+
+fn main_ls(dir_name: &String) -> i32 {
     match fs::read_dir(dir_name) {
         Ok(paths) => {
             for ent in paths.into_iter() {
                 println!("{}", ent.unwrap().path().display());
             }
+            0
+        }
+        Err(e) => print_io_error(&e),
+    }
+}
+
+// Distinguishes "not found" from "found but inaccessible" so k6 can assert
+// the difference between a missing preopen and a permission-denied one.
+// Gated like `filetype`, since both call `symlink_metadata`; the gate only
+// says the build probe for it compiled (and, where a runtime was on PATH,
+// ran without trapping), not that the call succeeds on the host.
+#[cfg(has_symlink_metadata)]
+fn main_exists(path: &String) -> i32 {
+    match fs::symlink_metadata(path) {
+        Ok(_) => {
+            println!("exists");
+            0
+        }
+        Err(e) => match e.raw_os_error() {
+            Some(error_code) if error_code == libc::ENOENT => {
+                println!("not found");
+                2
+            }
+            Some(error_code) if error_code == libc::EACCES => {
+                println!("found but inaccessible");
+                4
+            }
+            _ => print_io_error(&e),
+        },
+    }
+}
+
+// Prints the target of the symlink at `path`.
+#[cfg(has_readlink)]
+fn main_readlink(path: &String) -> i32 {
+    match fs::read_link(path) {
+        Ok(target) => {
+            println!("{}", target.display());
+            0
         }
-        Err(e) => {
-            if let Some(error_code) = e.raw_os_error() {
-                if error_code == ENOTDIR {
-                    println!("ENOTDIR");
-                } else {
-                    println!("errno=={}", error_code);
+        Err(e) => print_io_error(&e),
+    }
+}
+
+// Walks `dir_name` depth-first, printing every entry's path relative to it.
+// Siblings are sorted lexicographically so the listing is reproducible
+// across WASI runtimes that don't agree on raw `read_dir` ordering.
+fn main_walk(dir_name: &String, max_depth: Option<usize>) -> i32 {
+    walk(Path::new(dir_name), Path::new(""), 0, max_depth)
+}
+
+fn walk(dir: &Path, rel: &Path, depth: usize, max_depth: Option<usize>) -> i32 {
+    if let Some(max_depth) = max_depth
+        && depth > max_depth
+    {
+        return 0;
+    }
+
+    match fs::read_dir(dir) {
+        Ok(read_dir) => {
+            // Collect each entry's name and type and drop the `DirEntry`s
+            // (which hold the directory open), so the directory fd is closed
+            // before recursing and fd usage does not grow with depth.
+            let mut entries: Vec<_> = read_dir
+                .map(|ent| {
+                    let ent = ent.unwrap();
+                    (ent.file_name(), ent.file_type().unwrap().is_dir())
+                })
+                .collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+            for (name, is_dir) in entries {
+                let rel_path = rel.join(&name);
+                println!("{}", rel_path.display());
+
+                if is_dir {
+                    let code = walk(&dir.join(&name), &rel_path, depth + 1, max_depth);
+                    if code != 0 {
+                        return code;
+                    }
                 }
-            } else {
-                println!("unknown error");
             }
+            0
+        }
+        Err(e) => print_io_error(&e),
+    }
+}
+
+// Prints the capability set build.rs detected for this target, so a single
+// fixture binary can be diagnosed when run against a WASI host that's
+// missing one of the gated subcommands.
+fn main_caps() -> i32 {
+    println!("has_readlink: {}", cfg!(has_readlink));
+    println!("has_symlink_metadata: {}", cfg!(has_symlink_metadata));
+    0
+}
+
+// Classifies `path` as "file", "dir" or "symlink". `follow` selects between
+// `fs::metadata` (follows symlinks) and `fs::symlink_metadata` (doesn't), so
+// callers can compare the two across WASI hosts. Prints the resolved
+// category plus the raw WASI filetype so we can see what the host actually
+// returned.
+//
+// WASI's `filestat` carries no Unix mode bits, so an entry that isn't a
+// file, dir or symlink is one of the WASI filetype enum's other variants
+// (char/block device, socket, or unknown) rather than a reparse point. Use
+// `FileTypeExt` to report which.
+#[cfg(has_symlink_metadata)]
+fn main_filetype(path: &String, follow: bool) -> i32 {
+    let meta = if follow {
+        fs::metadata(path)
+    } else {
+        fs::symlink_metadata(path)
+    };
+
+    match meta {
+        Ok(meta) => {
+            let ft = meta.file_type();
+            let category = if meta.is_file() {
+                "file"
+            } else if meta.is_dir() {
+                "dir"
+            } else if meta.is_symlink() {
+                "symlink"
+            } else if ft.is_block_device() {
+                "block device"
+            } else if ft.is_char_device() {
+                "char device"
+            } else if ft.is_socket_dgram() {
+                "socket (dgram)"
+            } else if ft.is_socket_stream() {
+                "socket (stream)"
+            } else {
+                "unknown"
+            };
+            println!("{} (raw={:?})", category, ft);
+            0
         }
+        Err(e) => print_io_error(&e),
     }
 }
 
 extern crate libc;
 
-fn main_stat() {
+fn main_stat() -> i32 {
     unsafe {
         println!("stdin isatty: {}", libc::isatty(0) != 0);
         println!("stdout isatty: {}", libc::isatty(1) != 0);
         println!("stderr isatty: {}", libc::isatty(2) != 0);
         println!("/ isatty: {}", libc::isatty(3) != 0);
     }
+    0
 }